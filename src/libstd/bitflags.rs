@@ -17,13 +17,13 @@
 //! # Example
 //!
 //! ~~~rust
-//! bitflags!(Flags: u32 {
-//!     FlagA       = 0x00000001,
-//!     FlagB       = 0x00000010,
-//!     FlagC       = 0x00000100,
-//!     FlagABC     = FlagA.bits
-//!                 | FlagB.bits
-//!                 | FlagC.bits
+//! bitflags!(pub Flags: u32 {
+//!     pub FlagA       = 0x00000001,
+//!     pub FlagB       = 0x00000010,
+//!     pub FlagC       = 0x00000100,
+//!     pub FlagABC     = FlagA.bits
+//!                     | FlagB.bits
+//!                     | FlagC.bits
 //! })
 //!
 //! fn main() {
@@ -35,41 +35,62 @@
 //! }
 //! ~~~
 //!
-//! The generated `struct`s can also be extended with type and trait implementations:
+//! Attributes can be attached to the generated `struct` by placing them
+//! before the `$BitFlags` name, and to individual flags by placing them
+//! before the flag name, which is useful for documenting the flags or
+//! gating platform-specific ones behind `#[cfg(...)]`:
 //!
 //! ~~~rust
-//! use std::fmt;
+//! bitflags!(
+//!     #[doc = "My bitflags type."]
+//!     pub Flags: u32 {
+//!         #[doc = "The first flag."]
+//!         pub FlagA = 0x00000001,
+//!         pub FlagB = 0x00000010
+//!     }
+//! )
+//! ~~~
+//!
+//! Prefixing the invocation with `pub`, as above, makes the generated
+//! `struct` and its flags `pub`; omitting it keeps them private to the
+//! module housing the `bitflags!` invocation. This lets FFI binding crates
+//! keep a flags type entirely out of their public API when only the
+//! functions that consume it need to be exported.
 //!
-//! bitflags!(Flags: u32 {
-//!     FlagA   = 0x00000001,
-//!     FlagB   = 0x00000010
+//! The generated `struct`s can also be extended with type and trait implementations:
+//!
+//! ~~~rust
+//! bitflags!(pub Flags: u32 {
+//!     pub FlagA   = 0x00000001,
+//!     pub FlagB   = 0x00000010
 //! })
 //!
 //! impl Flags {
-//!     pub fn clear(&mut self) {
-//!         self.bits = 0;  // The `bits` field can be accessed from within the
-//!                         // same module where the `bitflags!` macro was invoked.
-//!     }
-//! }
-//!
-//! impl fmt::Show for Flags {
-//!     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-//!         write!(f.buf, "hi!")
+//!     pub fn as_u8(&self) -> u8 {
+//!         self.bits as u8  // The `bits` field can be accessed from within the
+//!                          // same module where the `bitflags!` macro was invoked.
 //!     }
 //! }
 //!
 //! fn main() {
 //!     let mut flags = FlagA | FlagB;
+//!     assert_eq!(format!("{}", flags).as_slice(), "FlagA | FlagB");
+//!     assert_eq!(flags.as_u8(), 0x11u8);
 //!     flags.clear();
 //!     assert!(flags.is_empty());
-//!     assert_eq!(format!("{}", flags).as_slice(), "hi!");
+//!     assert_eq!(format!("{}", flags).as_slice(), "empty");
 //! }
 //! ~~~
 //!
 //! # Derived traits
 //!
 //! The `Eq`, `TotalEq`, and `Clone` traits are automatically derived for the
-//! `struct` using the `deriving` attribute.
+//! `struct` using the `deriving` attribute. Additionally, `bitflags!` always
+//! implements `fmt::Show` for the `struct`, printing the bitwise-or-separated
+//! names of the flags that are set, or `empty` if none are set. This
+//! implementation cannot be overridden: writing your own `impl fmt::Show` for
+//! the same `struct` in the same module is a duplicate-impl error, so choose
+//! a different name if you need custom formatting.
 //!
 //! # Operators
 //!
@@ -78,42 +99,105 @@
 //! - `BitOr`: union
 //! - `BitAnd`: intersection
 //! - `Sub`: set difference
+//! - `Not`: complement
+//! - `BitXor`: symmetric difference
 //!
 //! # Methods
 //!
 //! The following methods are defined for the generated `struct`:
 //!
 //! - `empty`: an empty set of flags
+//! - `all`: the set containing all flags
 //! - `bits`: the raw value of the flags currently stored
+//! - `from_bits`: convert from underlying bit representation, unless that
+//!                representation contains bits that do not correspond to a flag
+//! - `from_bits_truncate`: convert from underlying bit representation, dropping
+//!                         any bits that do not correspond to flags
 //! - `is_empty`: `true` if no flags are currently stored
 //! - `intersects`: `true` if there are flags common to both `self` and `other`
 //! - `contains`: `true` all of the flags in `other` are contained within `self`
 //! - `insert`: inserts the specified flags in-place
 //! - `remove`: removes the specified flags in-place
+//! - `toggle`: toggles the specified flags in-place
+//! - `clear`: removes all flags in-place
+//! - `union`: returns the union between the two sets of flags
+//! - `intersection`: returns the intersection between the two sets of flags
+//! - `difference`: returns the set difference of the two sets of flags
+//!
+//! `union`, `intersection`, and `difference` are by-value equivalents of the
+//! `BitOr`, `BitAnd`, and `Sub` operators above, for code where operator
+//! overloading reads poorly; `insert`, `remove`, `toggle`, and `clear` remain
+//! the only in-place mutators, as this version of the language has no overloadable
+//! `|=`/`&=`/`-=` compound-assignment operators to mirror.
 
 #[macro_export]
 macro_rules! bitflags(
-    ($BitFlags:ident: $T:ty {
-        $($Flag:ident = $value:expr),+
+    ($(#[$attr:meta])* pub $BitFlags:ident: $T:ty {
+        $($(#[$Flag_attr:meta])* pub $Flag:ident = $value:expr),+
     }) => (
         #[deriving(Eq, TotalEq, Clone)]
+        $(#[$attr])*
         pub struct $BitFlags {
             bits: $T,
         }
 
-        $(pub static $Flag: $BitFlags = $BitFlags { bits: $value };)+
+        $($(#[$Flag_attr])* pub static $Flag: $BitFlags = $BitFlags { bits: $value };)+
 
+        bitflags_impl!($BitFlags, $T, $($Flag),+)
+    );
+    ($(#[$attr:meta])* $BitFlags:ident: $T:ty {
+        $($(#[$Flag_attr:meta])* $Flag:ident = $value:expr),+
+    }) => (
+        #[deriving(Eq, TotalEq, Clone)]
+        $(#[$attr])*
+        struct $BitFlags {
+            bits: $T,
+        }
+
+        $($(#[$Flag_attr])* static $Flag: $BitFlags = $BitFlags { bits: $value };)+
+
+        bitflags_impl!($BitFlags, $T, $($Flag),+)
+    )
+)
+
+// Shared by both arms of `bitflags!` above: everything here is independent
+// of whether the generated `struct` and flags ended up `pub` or private to
+// their module, so it only needs to be written once.
+#[macro_export]
+macro_rules! bitflags_impl(
+    ($BitFlags:ident, $T:ty, $($Flag:ident),+) => (
         impl $BitFlags {
             /// Returns an empty set of flags.
             pub fn empty() -> $BitFlags {
                 $BitFlags { bits: 0 }
             }
 
+            /// Returns the set containing all flags.
+            pub fn all() -> $BitFlags {
+                $BitFlags { bits: $($Flag.bits)|+ }
+            }
+
             /// Returns the raw value of the flags currently stored.
             pub fn bits(&self) -> $T {
                 self.bits
             }
 
+            /// Convert from underlying bit representation, unless that
+            /// representation contains bits that do not correspond to a flag.
+            pub fn from_bits(bits: $T) -> Option<$BitFlags> {
+                if (bits & !$BitFlags::all().bits()) != 0 {
+                    None
+                } else {
+                    Some($BitFlags { bits: bits })
+                }
+            }
+
+            /// Convert from underlying bit representation, dropping any bits
+            /// that do not correspond to flags.
+            pub fn from_bits_truncate(bits: $T) -> $BitFlags {
+                $BitFlags { bits: bits } & $BitFlags::all()
+            }
+
             /// Returns `true` if no flags are currently stored.
             pub fn is_empty(&self) -> bool {
                 *self == $BitFlags::empty()
@@ -138,6 +222,31 @@ macro_rules! bitflags(
             pub fn remove(&mut self, other: $BitFlags) {
                 self.bits &= !other.bits;
             }
+
+            /// Toggles the specified flags in-place.
+            pub fn toggle(&mut self, other: $BitFlags) {
+                self.bits ^= other.bits;
+            }
+
+            /// Removes all flags in-place.
+            pub fn clear(&mut self) {
+                self.bits = 0;
+            }
+
+            /// Returns the union of the two sets of flags.
+            pub fn union(self, other: $BitFlags) -> $BitFlags {
+                self | other
+            }
+
+            /// Returns the intersection between the two sets of flags.
+            pub fn intersection(self, other: $BitFlags) -> $BitFlags {
+                self & other
+            }
+
+            /// Returns the set difference of the two sets of flags.
+            pub fn difference(self, other: $BitFlags) -> $BitFlags {
+                self - other
+            }
         }
 
         impl BitOr<$BitFlags, $BitFlags> for $BitFlags {
@@ -163,22 +272,86 @@ macro_rules! bitflags(
                 $BitFlags { bits: self.bits & !other.bits }
             }
         }
+
+        impl Not<$BitFlags> for $BitFlags {
+            /// Returns the complement of this set of flags.
+            #[inline]
+            fn not(&self) -> $BitFlags {
+                $BitFlags { bits: !self.bits } & $BitFlags::all()
+            }
+        }
+
+        impl BitXor<$BitFlags, $BitFlags> for $BitFlags {
+            /// Returns the symmetric difference of the two sets of flags.
+            #[inline]
+            fn bitxor(&self, other: &$BitFlags) -> $BitFlags {
+                $BitFlags { bits: self.bits ^ other.bits }
+            }
+        }
+
+        impl ::std::fmt::Show for $BitFlags {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                if self.is_empty() {
+                    return write!(f.buf, "empty");
+                }
+                let mut first = true;
+                // Tracks which bits have already been printed so that an
+                // alias/composite flag (e.g. one OR-ing together several
+                // others) doesn't get listed alongside the flags that make
+                // it up once every one of its bits is already accounted for.
+                let mut accounted: $T = 0;
+                $(
+                    if self.contains($Flag) && ($Flag.bits & !accounted) != 0 {
+                        if !first {
+                            try!(write!(f.buf, " | "));
+                        }
+                        try!(write!(f.buf, "{}", stringify!($Flag)));
+                        first = false;
+                        accounted |= $Flag.bits;
+                    }
+                )+
+                Ok(())
+            }
+        }
     )
 )
 
 #[cfg(test)]
 mod tests {
-    use ops::{BitOr, BitAnd, Sub};
-
-    bitflags!(Flags: u32 {
-        FlagA       = 0x00000001,
-        FlagB       = 0x00000010,
-        FlagC       = 0x00000100,
-        FlagABC     = FlagA.bits
-                    | FlagB.bits
-                    | FlagC.bits
+    use ops::{BitOr, BitAnd, Sub, Not, BitXor};
+
+    bitflags!(pub Flags: u32 {
+        pub FlagA       = 0x00000001,
+        pub FlagB       = 0x00000010,
+        pub FlagC       = 0x00000100,
+        pub FlagABC     = FlagA.bits
+                        | FlagB.bits
+                        | FlagC.bits
+    })
+
+    bitflags!(
+        #[doc = "Another flags type, to test attribute support."]
+        pub AnotherSetOfFlags: uint {
+            #[doc = "The only flag."]
+            pub AnotherFlag = 0x00000001
+        }
+    )
+
+    bitflags!(PrivateFlags: u32 {
+        FlagX = 0x00000001
     })
 
+    #[test]
+    fn test_private_visibility() {
+        assert_eq!(PrivateFlags::empty().bits(), 0x00000000);
+        assert_eq!(FlagX.bits(), 0x00000001);
+    }
+
+    #[test]
+    fn test_attrs(){
+        assert_eq!(AnotherFlag.bits(), 0x00000001u);
+    }
+
     #[test]
     fn test_bits(){
         assert_eq!(Flags::empty().bits(), 0x00000000);
@@ -253,5 +426,78 @@ mod tests {
         assert!((e1 | e2) == FlagABC);   // union
         assert!((e1 & e2) == FlagC);     // intersection
         assert!((e1 - e2) == FlagA);     // set difference
+        assert!(!e1 == FlagB);           // complement
+        assert!((e1 ^ e2) == (FlagA | FlagB));  // symmetric difference
+    }
+
+    #[test]
+    fn test_toggle() {
+        let mut e1 = FlagA | FlagC;
+        let e2 = FlagA;
+        let e3 = FlagC;
+        e1.toggle(e2);
+        assert_eq!(e1, e3);
+    }
+
+    #[test]
+    fn test_all() {
+        assert_eq!(Flags::all().bits(), FlagABC.bits());
+    }
+
+    #[test]
+    fn test_show() {
+        assert_eq!(format!("{}", Flags::empty()).as_slice(), "empty");
+        assert_eq!(format!("{}", FlagA).as_slice(), "FlagA");
+        assert_eq!(format!("{}", FlagA | FlagB).as_slice(), "FlagA | FlagB");
+    }
+
+    #[test]
+    fn test_show_skips_redundant_alias() {
+        // FlagABC is declared as the union of FlagA, FlagB and FlagC, so once
+        // those three have each accounted for their bits, FlagABC itself
+        // contributes no new bits and should not also be printed.
+        assert_eq!(format!("{}", FlagABC).as_slice(), "FlagA | FlagB | FlagC");
+    }
+
+    #[test]
+    fn test_from_bits() {
+        assert_eq!(Flags::from_bits(0b1000_0000), None);
+        assert_eq!(Flags::from_bits(0b1), Some(FlagA));
+        assert_eq!(Flags::from_bits(0b1111_0000), None);
+    }
+
+    #[test]
+    fn test_from_bits_truncate() {
+        assert_eq!(Flags::from_bits_truncate(0b1000_0000), Flags::empty());
+        assert_eq!(Flags::from_bits_truncate(0b1), FlagA);
+        assert_eq!(Flags::from_bits_truncate(0x1001), FlagA);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut e1 = FlagA | FlagB;
+        e1.clear();
+        assert!(e1.is_empty());
+    }
+
+    #[test]
+    fn test_union() {
+        let e1 = FlagA;
+        let e2 = FlagB;
+        assert_eq!(e1.union(e2), FlagA | FlagB);
+    }
+
+    #[test]
+    fn test_intersection() {
+        let e1 = FlagA | FlagB;
+        let e2 = FlagA | FlagC;
+        assert_eq!(e1.intersection(e2), FlagA);
+    }
+
+    #[test]
+    fn test_difference() {
+        let e1 = FlagA | FlagB;
+        let e2 = FlagA;
+        assert_eq!(e1.difference(e2), FlagB);
     }
 }